@@ -0,0 +1,180 @@
+//! The `Attribute` trait and the machinery shared by every attribute decoder/encoder.
+
+use bytecodec::bytes::{BytesEncoder, RemainingBytesDecoder};
+use bytecodec::{ByteCount, Decode, Encode, Eos, Result, SizedEncode, TryTaggedDecode};
+
+/// The type of an attribute, i.e., its STUN/TURN codepoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct AttributeType(u16);
+impl AttributeType {
+    /// Makes a new `AttributeType` instance.
+    pub fn new(codepoint: u16) -> Self {
+        AttributeType(codepoint)
+    }
+
+    /// Returns the codepoint corresponding to this instance.
+    pub fn as_u16(self) -> u16 {
+        self.0
+    }
+}
+
+/// This trait represents a STUN/TURN attribute.
+pub trait Attribute: Clone + PartialEq {
+    /// The decoder of this attribute.
+    type Decoder: Default + Decode<Item = Self> + TryTaggedDecode<Tag = AttributeType>;
+
+    /// The encoder of this attribute.
+    type Encoder: Default + Encode<Item = Self> + SizedEncode;
+
+    /// Returns the type of this attribute.
+    fn get_type(&self) -> AttributeType;
+}
+
+/// A fallback attribute that carries an attribute's type and raw value verbatim.
+///
+/// Per [RFC 8489 §14], an agent that receives an unknown comprehension-optional
+/// attribute (i.e., one whose codepoint is `>= 0x8000`) must ignore it rather
+/// than reject the whole message, while an unknown comprehension-required
+/// attribute is still an error. `RawAttribute` lets callers such as a proxy
+/// preserve the former instead of failing to decode, by capturing the exact
+/// bytes of the attribute's value so it can be forwarded unchanged.
+///
+/// [RFC 8489 §14]: https://datatracker.ietf.org/doc/html/rfc8489#section-14
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RawAttribute {
+    attr_type: AttributeType,
+    value: Vec<u8>,
+}
+impl RawAttribute {
+    /// Makes a new `RawAttribute` instance.
+    pub fn new(attr_type: AttributeType, value: Vec<u8>) -> Self {
+        RawAttribute { attr_type, value }
+    }
+
+    /// Returns the type of this attribute.
+    pub fn get_type(&self) -> AttributeType {
+        self.attr_type
+    }
+
+    /// Returns the raw (unpadded) value of this attribute.
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+}
+impl Attribute for RawAttribute {
+    type Decoder = RawAttributeDecoder;
+    type Encoder = RawAttributeEncoder;
+
+    fn get_type(&self) -> AttributeType {
+        self.attr_type
+    }
+}
+
+/// [`RawAttribute`] decoder.
+#[derive(Debug, Default)]
+pub struct RawAttributeDecoder {
+    attr_type: AttributeType,
+    value: RemainingBytesDecoder,
+}
+impl RawAttributeDecoder {
+    /// Makes a new `RawAttributeDecoder` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+impl Decode for RawAttributeDecoder {
+    type Item = RawAttribute;
+
+    fn decode(&mut self, buf: &[u8], eos: Eos) -> Result<usize> {
+        track!(self.value.decode(buf, eos))
+    }
+
+    fn finish_decoding(&mut self) -> Result<Self::Item> {
+        let value = track!(self.value.finish_decoding())?;
+        Ok(RawAttribute::new(self.attr_type, value))
+    }
+
+    fn requiring_bytes(&self) -> ByteCount {
+        self.value.requiring_bytes()
+    }
+
+    fn is_idle(&self) -> bool {
+        self.value.is_idle()
+    }
+}
+impl TryTaggedDecode for RawAttributeDecoder {
+    type Tag = AttributeType;
+
+    fn try_start_decoding(&mut self, attr_type: Self::Tag) -> Result<bool> {
+        // Per RFC 8489 Section 14, only comprehension-optional attributes
+        // (codepoint >= 0x8000) may be silently ignored when unrecognized;
+        // an unknown comprehension-required attribute must still fail
+        // decoding, so `RawAttribute` only claims the former.
+        self.attr_type = attr_type;
+        Ok(attr_type.as_u16() >= 0x8000)
+    }
+}
+
+/// [`RawAttribute`] encoder.
+#[derive(Debug, Default)]
+pub struct RawAttributeEncoder {
+    value: BytesEncoder<Vec<u8>>,
+}
+impl RawAttributeEncoder {
+    /// Makes a new `RawAttributeEncoder` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+impl Encode for RawAttributeEncoder {
+    type Item = RawAttribute;
+
+    fn encode(&mut self, buf: &mut [u8], eos: Eos) -> Result<usize> {
+        track!(self.value.encode(buf, eos))
+    }
+
+    fn start_encoding(&mut self, item: Self::Item) -> Result<()> {
+        track!(self.value.start_encoding(item.value))
+    }
+
+    fn requiring_bytes(&self) -> ByteCount {
+        self.value.requiring_bytes()
+    }
+
+    fn is_idle(&self) -> bool {
+        self.value.is_idle()
+    }
+}
+impl SizedEncode for RawAttributeEncoder {
+    fn exact_requiring_bytes(&self) -> u64 {
+        self.value.exact_requiring_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytecodec::{DecodeExt, EncodeExt};
+
+    #[test]
+    fn raw_attribute_round_trips_unknown_payloads() {
+        let attr_type = AttributeType::new(0x8000);
+        let raw = RawAttribute::new(attr_type, vec![1, 2, 3, 0]);
+
+        let mut encoder = RawAttributeEncoder::default();
+        let bytes = encoder.encode_into_bytes(raw.clone()).unwrap();
+        assert_eq!(bytes, [1, 2, 3, 0]);
+
+        let mut decoder = RawAttributeDecoder::default();
+        decoder.try_start_decoding(attr_type).unwrap();
+        let decoded = decoder.decode_from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, raw);
+    }
+
+    #[test]
+    fn raw_attribute_rejects_unknown_comprehension_required_types() {
+        let mut decoder = RawAttributeDecoder::default();
+        let accepted = decoder.try_start_decoding(AttributeType::new(0x7fff)).unwrap();
+        assert!(!accepted);
+    }
+}
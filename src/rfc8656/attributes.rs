@@ -2,72 +2,15 @@
 //!
 //! [RFC 8656]: https://tools.ietf.org/html/rfc8656
 
+use bytecodec::bytes::{BytesEncoder, RemainingBytesDecoder};
 use bytecodec::fixnum::{U32beDecoder, U32beEncoder};
-use bytecodec::{ByteCount, Decode, Encode, Eos, Result, SizedEncode, TryTaggedDecode};
+use bytecodec::{
+    ByteCount, Decode, Encode, Eos, ErrorKind, Result, SizedEncode, TryTaggedDecode,
+};
 use std::fmt;
 
 use crate::attribute::{Attribute, AttributeType};
-
-macro_rules! impl_decode {
-    ($decoder:ty, $item:ident, $and_then:expr) => {
-        impl Decode for $decoder {
-            type Item = $item;
-
-            fn decode(&mut self, buf: &[u8], eos: Eos) -> Result<usize> {
-                track!(self.0.decode(buf, eos))
-            }
-
-            fn finish_decoding(&mut self) -> Result<Self::Item> {
-                track!(self.0.finish_decoding()).and_then($and_then)
-            }
-
-            fn requiring_bytes(&self) -> ByteCount {
-                self.0.requiring_bytes()
-            }
-
-            fn is_idle(&self) -> bool {
-                self.0.is_idle()
-            }
-        }
-        impl TryTaggedDecode for $decoder {
-            type Tag = AttributeType;
-
-            fn try_start_decoding(&mut self, attr_type: Self::Tag) -> Result<bool> {
-                Ok(attr_type.as_u16() == $item::CODEPOINT)
-            }
-        }
-    };
-}
-
-macro_rules! impl_encode {
-    ($encoder:ty, $item:ty, $map_from:expr) => {
-        impl Encode for $encoder {
-            type Item = $item;
-
-            fn encode(&mut self, buf: &mut [u8], eos: Eos) -> Result<usize> {
-                track!(self.0.encode(buf, eos))
-            }
-
-            #[allow(clippy::redundant_closure_call)]
-            fn start_encoding(&mut self, item: Self::Item) -> Result<()> {
-                track!(self.0.start_encoding($map_from(item).into()))
-            }
-
-            fn requiring_bytes(&self) -> ByteCount {
-                self.0.requiring_bytes()
-            }
-
-            fn is_idle(&self) -> bool {
-                self.0.is_idle()
-            }
-        }
-        impl SizedEncode for $encoder {
-            fn exact_requiring_bytes(&self) -> u64 {
-                self.0.exact_requiring_bytes()
-            }
-        }
-    };
-}
+use stun_codec_derive::StunAttribute;
 
 /// The family of an IP address, either IPv4 or IPv6.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -76,6 +19,13 @@ pub enum AddressFamily {
     V4,
     /// Version 6 of IP
     V6,
+    /// An address family that is not known to this crate.
+    ///
+    /// This preserves the raw family byte so that callers (e.g. a TURN
+    /// server) can decide what to do with it instead of failing to decode
+    /// the whole message, e.g. responding with the RFC 8656 error code 440
+    /// ("Address Family not Supported").
+    Unknown(u8),
 }
 
 impl fmt::Display for AddressFamily {
@@ -83,6 +33,7 @@ impl fmt::Display for AddressFamily {
         match self {
             AddressFamily::V4 => write!(f, "IPv4"),
             AddressFamily::V6 => write!(f, "IPv6"),
+            AddressFamily::Unknown(fam) => write!(f, "Unknown({})", fam),
         }
     }
 }
@@ -93,12 +44,10 @@ const FAMILY_IPV6: u8 = 2;
 /// This attribute is used in Allocate and Refresh requests to specify the address type requested by the client.
 ///
 /// See <https://datatracker.ietf.org/doc/html/rfc8656#name-requested-address-family> for details.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StunAttribute)]
+#[stun(codepoint = 0x0017, inner = AddressFamily)]
 pub struct RequestedAddressFamily(AddressFamily);
 impl RequestedAddressFamily {
-    /// The codepoint of the type of the attribute.
-    pub const CODEPOINT: u16 = 0x0017;
-
     /// Makes a new `RequestedAddressFamily` instance.
     pub fn new(fam: AddressFamily) -> Self {
         RequestedAddressFamily(fam)
@@ -109,54 +58,14 @@ impl RequestedAddressFamily {
         self.0
     }
 }
-impl Attribute for RequestedAddressFamily {
-    type Decoder = RequestedAddressFamilyDecoder;
-    type Encoder = RequestedAddressFamilyEncoder;
-
-    fn get_type(&self) -> AttributeType {
-        AttributeType::new(Self::CODEPOINT)
-    }
-}
-
-/// [`RequestedAddressFamily`] decoder.
-#[derive(Debug, Default)]
-pub struct RequestedAddressFamilyDecoder(AddressFamilyDecoder);
-impl RequestedAddressFamilyDecoder {
-    /// Makes a new `RequestedAddressFamilyDecoder` instance.
-    pub fn new() -> Self {
-        Self::default()
-    }
-}
-impl_decode!(
-    RequestedAddressFamilyDecoder,
-    RequestedAddressFamily,
-    |item| Ok(RequestedAddressFamily(item))
-);
-
-/// [`RequestedAddressFamily`] encoder.
-#[derive(Debug, Default)]
-pub struct RequestedAddressFamilyEncoder(AddressFamilyEncoder);
-impl RequestedAddressFamilyEncoder {
-    /// Makes a new `RequestedAddressFamilyEncoder` instance.
-    pub fn new() -> Self {
-        Self::default()
-    }
-}
-impl_encode!(
-    RequestedAddressFamilyEncoder,
-    RequestedAddressFamily,
-    |item: Self::Item| { item.0 }
-);
 
 /// This attribute is used by clients to request the allocation of an IPv4 and IPv6 address type from a server.
 ///
 /// See <https://datatracker.ietf.org/doc/html/rfc8656#name-additional-address-family> for details.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StunAttribute)]
+#[stun(codepoint = 0x8000, inner = AddressFamily)]
 pub struct AdditionalAddressFamily(AddressFamily);
 impl AdditionalAddressFamily {
-    /// The codepoint of the type of the attribute.
-    pub const CODEPOINT: u16 = 0x8000;
-
     /// Makes a new `AdditionalAddressFamily` instance.
     pub fn new(fam: AddressFamily) -> Self {
         AdditionalAddressFamily(fam)
@@ -167,33 +76,163 @@ impl AdditionalAddressFamily {
         self.0
     }
 }
-impl Attribute for AdditionalAddressFamily {
-    type Decoder = AdditionalAddressFamilyDecoder;
-    type Encoder = AdditionalAddressFamilyEncoder;
+
+/// This attribute is used by a server to signal a per-family allocation failure when a client requested more than one address family via [`AdditionalAddressFamily`].
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc8656#name-address-error-code> for details.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AddressErrorCode {
+    family: AddressFamily,
+    code: u16,
+    reason: String,
+}
+impl AddressErrorCode {
+    /// The codepoint of the type of the attribute.
+    pub const CODEPOINT: u16 = 0x8001;
+
+    /// Makes a new `AddressErrorCode` instance.
+    pub fn new(family: AddressFamily, code: u16, reason: String) -> Self {
+        AddressErrorCode {
+            family,
+            code,
+            reason,
+        }
+    }
+
+    /// Returns the address family that the error applies to.
+    pub fn address_family(&self) -> AddressFamily {
+        self.family
+    }
+
+    /// Returns the error code (e.g., `440`).
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    /// Returns the reason phrase of the error.
+    pub fn reason_phrase(&self) -> &str {
+        &self.reason
+    }
+}
+impl Attribute for AddressErrorCode {
+    type Decoder = AddressErrorCodeDecoder;
+    type Encoder = AddressErrorCodeEncoder;
 
     fn get_type(&self) -> AttributeType {
         AttributeType::new(Self::CODEPOINT)
     }
 }
 
-/// [`AdditionalAddressFamily`] decoder.
+/// [`AddressErrorCode`] decoder.
 #[derive(Debug, Default)]
-pub struct AdditionalAddressFamilyDecoder(AddressFamilyDecoder);
+pub struct AddressErrorCodeDecoder {
+    bytes: RemainingBytesDecoder,
+}
+impl AddressErrorCodeDecoder {
+    /// Makes a new `AddressErrorCodeDecoder` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+impl Decode for AddressErrorCodeDecoder {
+    type Item = AddressErrorCode;
+
+    fn decode(&mut self, buf: &[u8], eos: Eos) -> Result<usize> {
+        track!(self.bytes.decode(buf, eos))
+    }
+
+    fn finish_decoding(&mut self) -> Result<Self::Item> {
+        let bytes = track!(self.bytes.finish_decoding())?;
+        track_assert!(bytes.len() >= 4, ErrorKind::InvalidInput; bytes.len());
+
+        let family = match bytes[0] {
+            FAMILY_IPV4 => AddressFamily::V4,
+            FAMILY_IPV6 => AddressFamily::V6,
+            fam => AddressFamily::Unknown(fam),
+        };
+        let class = u16::from(bytes[2] & 0b0000_0111);
+        let number = u16::from(bytes[3]);
+        let code = class * 100 + number;
 
-impl_decode!(
-    AdditionalAddressFamilyDecoder,
-    AdditionalAddressFamily,
-    |item| Ok(AdditionalAddressFamily(item))
-);
+        let reason = String::from_utf8_lossy(trim_padding(&bytes[4..])).into_owned();
+
+        Ok(AddressErrorCode::new(family, code, reason))
+    }
 
-/// [`AdditionalAddressFamily`] encoder.
+    fn requiring_bytes(&self) -> ByteCount {
+        self.bytes.requiring_bytes()
+    }
+
+    fn is_idle(&self) -> bool {
+        self.bytes.is_idle()
+    }
+}
+impl TryTaggedDecode for AddressErrorCodeDecoder {
+    type Tag = AttributeType;
+
+    fn try_start_decoding(&mut self, attr_type: Self::Tag) -> Result<bool> {
+        Ok(attr_type.as_u16() == AddressErrorCode::CODEPOINT)
+    }
+}
+
+/// [`AddressErrorCode`] encoder.
 #[derive(Debug, Default)]
-pub struct AdditionalAddressFamilyEncoder(AddressFamilyEncoder);
-impl_encode!(
-    AdditionalAddressFamilyEncoder,
-    AdditionalAddressFamily,
-    |item: Self::Item| { item.0 }
-);
+pub struct AddressErrorCodeEncoder {
+    bytes: BytesEncoder<Vec<u8>>,
+}
+impl AddressErrorCodeEncoder {
+    /// Makes a new `AddressErrorCodeEncoder` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+impl Encode for AddressErrorCodeEncoder {
+    type Item = AddressErrorCode;
+
+    fn encode(&mut self, buf: &mut [u8], eos: Eos) -> Result<usize> {
+        track!(self.bytes.encode(buf, eos))
+    }
+
+    fn start_encoding(&mut self, item: Self::Item) -> Result<()> {
+        let fam_byte = match item.family {
+            AddressFamily::V4 => FAMILY_IPV4,
+            AddressFamily::V6 => FAMILY_IPV6,
+            AddressFamily::Unknown(fam) => fam,
+        };
+        let class = (item.code / 100) as u8 & 0b0000_0111;
+        let number = (item.code % 100) as u8;
+
+        let mut bytes = vec![fam_byte, 0, class, number];
+        bytes.extend_from_slice(item.reason.as_bytes());
+        while bytes.len() % 4 != 0 {
+            bytes.push(0);
+        }
+
+        track!(self.bytes.start_encoding(bytes))
+    }
+
+    fn requiring_bytes(&self) -> ByteCount {
+        self.bytes.requiring_bytes()
+    }
+
+    fn is_idle(&self) -> bool {
+        self.bytes.is_idle()
+    }
+}
+impl SizedEncode for AddressErrorCodeEncoder {
+    fn exact_requiring_bytes(&self) -> u64 {
+        self.bytes.exact_requiring_bytes()
+    }
+}
+
+/// Strips the trailing NUL padding bytes added to round a reason phrase up to a 4-byte boundary.
+fn trim_padding(bytes: &[u8]) -> &[u8] {
+    let end = bytes
+        .iter()
+        .rposition(|&b| b != 0)
+        .map_or(0, |pos| pos + 1);
+    &bytes[..end]
+}
 
 /// [`RequestedAddressFamily`] decoder.
 #[derive(Debug, Default)]
@@ -214,11 +253,7 @@ impl Decode for AddressFamilyDecoder {
         match fam {
             FAMILY_IPV4 => Ok(AddressFamily::V4),
             FAMILY_IPV6 => Ok(AddressFamily::V6),
-            family => track_panic!(
-                bytecodec::ErrorKind::InvalidInput,
-                "Unknown address family: {}",
-                family
-            ),
+            family => Ok(AddressFamily::Unknown(family)),
         }
     }
 
@@ -248,6 +283,7 @@ impl Encode for AddressFamilyEncoder {
         let fam_byte = match item {
             AddressFamily::V4 => FAMILY_IPV4,
             AddressFamily::V6 => FAMILY_IPV6,
+            AddressFamily::Unknown(fam) => fam,
         };
 
         let bytes = [fam_byte, 0, 0, 0];
@@ -292,4 +328,68 @@ mod tests {
         let fam = decoder.decode_from_bytes(&[2, 0, 0, 0]).unwrap();
         assert_eq!(fam, AddressFamily::V6);
     }
+
+    #[test]
+    fn address_family_round_trips_unknown_values() {
+        let mut decoder = AddressFamilyDecoder::default();
+        let fam = decoder.decode_from_bytes(&[3, 0, 0, 0]).unwrap();
+        assert_eq!(fam, AddressFamily::Unknown(3));
+
+        let mut encoder = AddressFamilyEncoder::default();
+        let bytes = encoder.encode_into_bytes(fam).unwrap();
+        assert_eq!(bytes, [3, 0, 0, 0]);
+    }
+
+    #[test]
+    fn derived_attribute_encodes_like_before() {
+        let mut encoder = RequestedAddressFamilyEncoder::default();
+        let bytes = encoder
+            .encode_into_bytes(RequestedAddressFamily::new(AddressFamily::V4))
+            .unwrap();
+        assert_eq!(bytes, [1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn address_error_code_round_trips() {
+        let attr = AddressErrorCode::new(
+            AddressFamily::V6,
+            440,
+            "Address Family not Supported".to_owned(),
+        );
+
+        let mut encoder = AddressErrorCodeEncoder::default();
+        let bytes = encoder.encode_into_bytes(attr.clone()).unwrap();
+        assert_eq!(bytes.len() % 4, 0);
+
+        let mut decoder = AddressErrorCodeDecoder::default();
+        let decoded = decoder.decode_from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, attr);
+    }
+
+    #[test]
+    fn address_error_code_uses_a_four_byte_header() {
+        // family(1) + reserved(1) + reserved/class(1) + number(1), then the
+        // (padded) reason phrase, mirroring the standard ERROR-CODE layout.
+        let mut encoder = AddressErrorCodeEncoder::default();
+        let bytes = encoder
+            .encode_into_bytes(AddressErrorCode::new(
+                AddressFamily::V6,
+                440,
+                "AB".to_owned(),
+            ))
+            .unwrap();
+        assert_eq!(bytes, [2, 0, 4, 40, b'A', b'B', 0, 0]);
+    }
+
+    #[test]
+    fn address_error_code_preserves_unknown_family() {
+        let attr = AddressErrorCode::new(AddressFamily::Unknown(9), 440, "".to_owned());
+
+        let mut encoder = AddressErrorCodeEncoder::default();
+        let bytes = encoder.encode_into_bytes(attr.clone()).unwrap();
+
+        let mut decoder = AddressErrorCodeDecoder::default();
+        let decoded = decoder.decode_from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, attr);
+    }
 }
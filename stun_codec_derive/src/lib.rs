@@ -0,0 +1,167 @@
+//! The `#[derive(StunAttribute)]` proc-macro used by `stun_codec`.
+//!
+//! A STUN attribute struct that wraps a single inner value only needs to
+//! say which codepoint it owns and which codec implements its wire format;
+//! this macro fills in the `Attribute` impl and the newtype
+//! `{Name}Decoder`/`{Name}Encoder` pair (plus their `Decode`/
+//! `TryTaggedDecode`/`Encode`/`SizedEncode` forwarding impls) that would
+//! otherwise be copy-pasted by hand for every attribute.
+//!
+//! ```ignore
+//! #[derive(StunAttribute)]
+//! #[stun(codepoint = 0x0017, inner = AddressFamily)]
+//! pub struct RequestedAddressFamily(AddressFamily);
+//! ```
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitInt};
+
+#[proc_macro_derive(StunAttribute, attributes(stun))]
+pub fn derive_stun_attribute(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let name = input.ident;
+    let field_type = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                fields.unnamed.first().unwrap().ty.clone()
+            }
+            _ => panic!("#[derive(StunAttribute)] only supports single-field tuple structs"),
+        },
+        _ => panic!("#[derive(StunAttribute)] only supports structs"),
+    };
+
+    let (codepoint, inner_type) = parse_stun_attr(&input.attrs);
+    let field_type_name = quote!(#field_type).to_string().replace(' ', "");
+    assert_eq!(
+        field_type_name,
+        inner_type.to_string(),
+        "#[stun(inner = {})] does not match the struct's field type `{}`",
+        inner_type,
+        field_type_name,
+    );
+
+    let inner_decoder = format_ident!("{}Decoder", inner_type);
+    let inner_encoder = format_ident!("{}Encoder", inner_type);
+    let decoder_name = format_ident!("{}Decoder", name);
+    let encoder_name = format_ident!("{}Encoder", name);
+
+    let expanded = quote! {
+        impl #name {
+            /// The codepoint of the type of the attribute.
+            pub const CODEPOINT: u16 = #codepoint;
+        }
+
+        impl crate::attribute::Attribute for #name {
+            type Decoder = #decoder_name;
+            type Encoder = #encoder_name;
+
+            fn get_type(&self) -> crate::attribute::AttributeType {
+                crate::attribute::AttributeType::new(Self::CODEPOINT)
+            }
+        }
+
+        #[doc = concat!("[`", stringify!(#name), "`] decoder.")]
+        #[derive(Debug, Default)]
+        pub struct #decoder_name(#inner_decoder);
+        impl #decoder_name {
+            #[doc = concat!("Makes a new `", stringify!(#decoder_name), "` instance.")]
+            pub fn new() -> Self {
+                Self::default()
+            }
+        }
+        impl bytecodec::Decode for #decoder_name {
+            type Item = #name;
+
+            fn decode(&mut self, buf: &[u8], eos: bytecodec::Eos) -> bytecodec::Result<usize> {
+                track!(self.0.decode(buf, eos))
+            }
+
+            fn finish_decoding(&mut self) -> bytecodec::Result<Self::Item> {
+                track!(self.0.finish_decoding()).map(#name)
+            }
+
+            fn requiring_bytes(&self) -> bytecodec::ByteCount {
+                self.0.requiring_bytes()
+            }
+
+            fn is_idle(&self) -> bool {
+                self.0.is_idle()
+            }
+        }
+        impl bytecodec::TryTaggedDecode for #decoder_name {
+            type Tag = crate::attribute::AttributeType;
+
+            fn try_start_decoding(&mut self, attr_type: Self::Tag) -> bytecodec::Result<bool> {
+                Ok(attr_type.as_u16() == #name::CODEPOINT)
+            }
+        }
+
+        #[doc = concat!("[`", stringify!(#name), "`] encoder.")]
+        #[derive(Debug, Default)]
+        pub struct #encoder_name(#inner_encoder);
+        impl #encoder_name {
+            #[doc = concat!("Makes a new `", stringify!(#encoder_name), "` instance.")]
+            pub fn new() -> Self {
+                Self::default()
+            }
+        }
+        impl bytecodec::Encode for #encoder_name {
+            type Item = #name;
+
+            fn encode(&mut self, buf: &mut [u8], eos: bytecodec::Eos) -> bytecodec::Result<usize> {
+                track!(self.0.encode(buf, eos))
+            }
+
+            fn start_encoding(&mut self, item: Self::Item) -> bytecodec::Result<()> {
+                track!(self.0.start_encoding(item.0))
+            }
+
+            fn requiring_bytes(&self) -> bytecodec::ByteCount {
+                self.0.requiring_bytes()
+            }
+
+            fn is_idle(&self) -> bool {
+                self.0.is_idle()
+            }
+        }
+        impl bytecodec::SizedEncode for #encoder_name {
+            fn exact_requiring_bytes(&self) -> u64 {
+                self.0.exact_requiring_bytes()
+            }
+        }
+    };
+
+    expanded.into()
+}
+
+fn parse_stun_attr(attrs: &[syn::Attribute]) -> (LitInt, Ident) {
+    let mut codepoint = None;
+    let mut inner = None;
+
+    for attr in attrs {
+        if !attr.path().is_ident("stun") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("codepoint") {
+                let value = meta.value()?;
+                codepoint = Some(value.parse()?);
+            } else if meta.path.is_ident("inner") {
+                let value = meta.value()?;
+                inner = Some(value.parse()?);
+            }
+            Ok(())
+        })
+        .expect("malformed `#[stun(..)]` attribute");
+    }
+
+    (
+        codepoint.expect("missing `#[stun(codepoint = ..)]`"),
+        inner.unwrap_or_else(|| Ident::new("unknown", Span::call_site())),
+    )
+}